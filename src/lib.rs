@@ -1,6 +1,129 @@
-use adler32::RollingAdler32;
 use bitvec::prelude::*;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+// two (base, modulus) pairs, packed into the high/low half of a u64 key
+const BASE1: u64 = 131;
+const MOD1: u64 = 4_294_967_291;
+const BASE2: u64 = 137;
+const MOD2: u64 = 4_294_967_279;
+
+fn mod_pow(mut base: u64, mut exp: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    base %= modulus;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = (result * base) % modulus;
+        }
+        exp >>= 1;
+        base = (base * base) % modulus;
+    }
+    result
+}
+
+/// A token the rolling fingerprint can index; non-primitive `T` can
+/// implement this via [`hash_token_value`].
+pub trait Fingerprintable: Copy + Eq {
+    fn token_value(&self) -> u64;
+}
+
+/// Hashes an arbitrary `Hash` value down to the `u64` a [`Fingerprintable`]
+/// impl is expected to return, for token types that aren't plain integers.
+pub fn hash_token_value<T: Hash>(token: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+    hasher.finish()
+}
+
+macro_rules! impl_fingerprintable_cast {
+    ($($t:ty),*) => {
+        $(
+            impl Fingerprintable for $t {
+                #[inline]
+                fn token_value(&self) -> u64 {
+                    *self as u64
+                }
+            }
+        )*
+    };
+}
+
+impl_fingerprintable_cast!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, char, bool);
+
+/// Rolling Karp-Rabin fingerprint over a fixed-length window of tokens,
+/// computed with two independent polynomials and packed into a single
+/// `u64` hash map key. Works over any `T: Fingerprintable`, not just bytes.
+struct RollingFingerprint {
+    pow1: u64,
+    pow2: u64,
+    hash1: u64,
+    hash2: u64,
+}
+
+impl RollingFingerprint {
+    fn new<T: Fingerprintable>(window: &[T], pow1: u64, pow2: u64) -> Self {
+        let mut hash1 = 0;
+        let mut hash2 = 0;
+        for t in window {
+            let v = t.token_value();
+            hash1 = (hash1 * BASE1 + v % MOD1) % MOD1;
+            hash2 = (hash2 * BASE2 + v % MOD2) % MOD2;
+        }
+        RollingFingerprint {
+            pow1,
+            pow2,
+            hash1,
+            hash2,
+        }
+    }
+
+    fn roll<T: Fingerprintable>(&mut self, out: &T, input: &T) {
+        let out = out.token_value();
+        let input = input.token_value();
+        self.hash1 = ((self.hash1 + MOD1 - (out % MOD1 * self.pow1) % MOD1) * BASE1
+            + input % MOD1)
+            % MOD1;
+        self.hash2 = ((self.hash2 + MOD2 - (out % MOD2 * self.pow2) % MOD2) * BASE2
+            + input % MOD2)
+            % MOD2;
+    }
+
+    fn key(&self) -> u64 {
+        (self.hash1 << 32) | self.hash2
+    }
+}
+
+/// Yields the maximal `[start, end)` spans of unmarked (`false`) bits in a
+/// mark bitvec.
+struct UnmarkedSpans<'a> {
+    mark: &'a BitSlice,
+    pos: usize,
+}
+
+impl<'a> UnmarkedSpans<'a> {
+    fn new(mark: &'a BitSlice) -> Self {
+        UnmarkedSpans { mark, pos: 0 }
+    }
+}
+
+impl<'a> Iterator for UnmarkedSpans<'a> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let len = self.mark.len();
+        if self.pos >= len {
+            return None;
+        }
+        let start = self.pos + self.mark[self.pos..].first_zero()?;
+        let end = match self.mark[start..].first_one() {
+            Some(rel) => start + rel,
+            None => len,
+        };
+        self.pos = end;
+        Some((start, end))
+    }
+}
 
 #[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Match {
@@ -9,90 +132,81 @@ pub struct Match {
     pub length: usize,
 }
 
-struct RkrGst<'a> {
-    pattern: &'a [u8],
-    text: &'a [u8],
+/// A [`Match`] found against one text of a corpus passed to [`run_many`].
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub struct CorpusMatch {
+    pub text_id: usize,
+    pub pattern_index: usize,
+    pub text_index: usize,
+    pub length: usize,
+}
+
+struct RkrGst<'a, T> {
+    pattern: &'a [T],
+    texts: &'a [&'a [T]],
     pattern_mark: BitVec,
-    text_mark: BitVec,
-    matches: Vec<Match>,
-    result: Vec<Match>,
+    text_marks: Vec<BitVec>,
+    matches: Vec<CorpusMatch>,
+    result: Vec<CorpusMatch>,
 }
 
-impl<'a> RkrGst<'a> {
+impl<'a, T: Fingerprintable> RkrGst<'a, T> {
     fn scan_pattern(&mut self, search_length: usize) -> usize {
-        // map text hashes => text index
-        let mut map: HashMap<u32, Vec<usize>> = HashMap::new();
-        let mut i = 0;
-        while (i + search_length) <= self.text.len() {
-            // jump to first unmarked token
-            for j in i..(i + search_length) {
-                if self.text_mark[j] {
-                    i = j + 1;
-                    break;
+        // B^(L-1) for each polynomial, same for every window this phase
+        let pow1 = mod_pow(BASE1, search_length.saturating_sub(1) as u64, MOD1);
+        let pow2 = mod_pow(BASE2, search_length.saturating_sub(1) as u64, MOD2);
+
+        // map fingerprints => (text id, text index), built once across all
+        // texts so the index is shared by every text in the corpus
+        let mut map: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+        for (text_id, text) in self.texts.iter().enumerate() {
+            for (start, end) in UnmarkedSpans::new(&self.text_marks[text_id]) {
+                if end - start < search_length {
+                    continue;
                 }
-            }
-            if i + search_length > self.text.len() {
-                break;
-            }
 
-            // text[i..i+search_length] is unmarked
-            let mut hash = RollingAdler32::new();
-            for j in i..(i + search_length) {
-                hash.update(self.text[j]);
-            }
+                // text[start..end] is unmarked
+                let mut i = start;
+                let mut hash = RollingFingerprint::new(&text[i..(i + search_length)], pow1, pow2);
 
-            // advance until next marked
-            loop {
-                if self.text_mark[i + search_length - 1] {
-                    break;
-                }
-                map.entry(hash.hash()).or_insert_with(Vec::new).push(i);
-                i += 1;
-                if i + search_length > self.text.len() {
-                    break;
+                loop {
+                    map.entry(hash.key())
+                        .or_insert_with(Vec::new)
+                        .push((text_id, i));
+                    i += 1;
+                    if i + search_length > end {
+                        break;
+                    }
+                    hash.roll(&text[i - 1], &text[i + search_length - 1]);
                 }
-                hash.remove(search_length, self.text[i - 1]);
-                hash.update(self.text[i + search_length - 1]);
             }
         }
 
         // search patterns
         self.matches.clear();
         let mut max_match = 0;
-        i = 0;
-        while (i + search_length) <= self.pattern.len() {
-            // jump to first unmarked token
-            for j in i..(i + search_length) {
-                if self.pattern_mark[j] {
-                    i = j + 1;
-                    break;
-                }
-            }
-            if i + search_length > self.pattern.len() {
-                break;
+        for (start, end) in UnmarkedSpans::new(&self.pattern_mark) {
+            if end - start < search_length {
+                continue;
             }
 
-            // pattern[i..i+search_length] is unmarked
-            let mut hash = RollingAdler32::new();
-            for j in i..(i + search_length) {
-                hash.update(self.pattern[j]);
-            }
+            // pattern[start..end] is unmarked
+            let mut i = start;
+            let mut hash = RollingFingerprint::new(&self.pattern[i..(i + search_length)], pow1, pow2);
 
-            // advance until next marked
             loop {
-                if self.pattern_mark[i + search_length - 1] {
-                    break;
-                }
-                if map.contains_key(&hash.hash()) {
+                if let Some(candidates) = map.get(&hash.key()) {
                     // found a match, check that it really matches
                     // and try to extend
-                    for text_index in &map[&hash.hash()] {
+                    for &(text_id, text_index) in candidates {
+                        let text = self.texts[text_id];
+                        let text_mark = &self.text_marks[text_id];
                         let pattern_index = i;
                         let mut k = 0;
-                        while *text_index + k < self.text.len()
+                        while text_index + k < text.len()
                             && pattern_index + k < self.pattern.len()
-                            && self.text[text_index + k] == self.pattern[pattern_index + k]
-                            && !self.text_mark[text_index + k]
+                            && text[text_index + k] == self.pattern[pattern_index + k]
+                            && !text_mark[text_index + k]
                             && !self.pattern_mark[pattern_index + k]
                         {
                             k += 1;
@@ -103,9 +217,10 @@ impl<'a> RkrGst<'a> {
                         }
 
                         if k >= search_length {
-                            self.matches.push(Match {
+                            self.matches.push(CorpusMatch {
+                                text_id,
                                 pattern_index,
-                                text_index: *text_index,
+                                text_index,
                                 length: k,
                             });
                             max_match = std::cmp::max(max_match, k);
@@ -114,11 +229,10 @@ impl<'a> RkrGst<'a> {
                 }
 
                 i += 1;
-                if i + search_length > self.pattern.len() {
+                if i + search_length > end {
                     break;
                 }
-                hash.remove(search_length, self.pattern[i - 1]);
-                hash.update(self.pattern[i + search_length - 1]);
+                hash.roll(&self.pattern[i - 1], &self.pattern[i + search_length - 1]);
             }
         }
 
@@ -130,17 +244,21 @@ impl<'a> RkrGst<'a> {
         self.matches.sort_by(|a, b| b.length.cmp(&a.length));
         for m in &self.matches {
             let mut unmarked = true;
-            for i in 0..m.length {
-                if self.text_mark[m.text_index + i] || self.pattern_mark[m.pattern_index + i] {
-                    unmarked = false;
-                    break;
+            {
+                let text_mark = &self.text_marks[m.text_id];
+                for i in 0..m.length {
+                    if text_mark[m.text_index + i] || self.pattern_mark[m.pattern_index + i] {
+                        unmarked = false;
+                        break;
+                    }
                 }
             }
 
             if unmarked {
                 self.result.push(*m);
+                let text_mark = &mut self.text_marks[m.text_id];
                 for i in 0..m.length {
-                    self.text_mark.set(m.text_index + i, true);
+                    text_mark.set(m.text_index + i, true);
                     self.pattern_mark.set(m.pattern_index + i, true);
                 }
             }
@@ -149,18 +267,39 @@ impl<'a> RkrGst<'a> {
     }
 }
 
-pub fn run(
-    pattern: &[u8],
-    text: &[u8],
+/// Run greedy string tiling over any token stream `T: Fingerprintable`, not
+/// just bytes.
+pub fn run<T: Fingerprintable>(
+    pattern: &[T],
+    text: &[T],
     initial_search_length: usize,
     minimum_match_length: usize,
 ) -> Vec<Match> {
+    run_many(pattern, &[text], initial_search_length, minimum_match_length)
+        .into_iter()
+        .map(|m| Match {
+            pattern_index: m.pattern_index,
+            text_index: m.text_index,
+            length: m.length,
+        })
+        .collect()
+}
+
+/// Tile `pattern` against a whole corpus of `texts` in a single pass, with
+/// the window index built once and shared across every text. Tiles never
+/// cross text boundaries.
+pub fn run_many<T: Fingerprintable>(
+    pattern: &[T],
+    texts: &[&[T]],
+    initial_search_length: usize,
+    minimum_match_length: usize,
+) -> Vec<CorpusMatch> {
     let mut s = initial_search_length;
     let mut params = RkrGst {
         pattern,
-        text,
+        texts,
         pattern_mark: bitvec![0; pattern.len()],
-        text_mark: bitvec![0; text.len()],
+        text_marks: texts.iter().map(|t| bitvec![0; t.len()]).collect(),
         matches: vec![],
         result: vec![],
     };
@@ -208,6 +347,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn corpus_match() {
+        assert_eq!(
+            run_many(
+                "lowerlow".as_bytes(),
+                &["yellow".as_bytes(), "lowlow".as_bytes()],
+                3,
+                2
+            ),
+            vec![
+                CorpusMatch {
+                    text_id: 0,
+                    pattern_index: 0,
+                    text_index: 3,
+                    length: 3
+                },
+                CorpusMatch {
+                    text_id: 1,
+                    pattern_index: 5,
+                    text_index: 0,
+                    length: 3
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn token_stream_match() {
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+        enum Tok {
+            Let,
+            Ident,
+            Eq,
+            Num,
+            Semi,
+        }
+        use Tok::*;
+
+        impl Fingerprintable for Tok {
+            fn token_value(&self) -> u64 {
+                hash_token_value(self)
+            }
+        }
+
+        // `let x = 1;` and `let y = 1;` tile identically once identifiers
+        // are tokenized, even though the underlying names differ.
+        let pattern = [Let, Ident, Eq, Num, Semi];
+        let text = [Num, Semi, Let, Ident, Eq, Num, Semi];
+        assert_eq!(
+            run(&pattern, &text, 3, 2),
+            vec![Match {
+                pattern_index: 0,
+                text_index: 2,
+                length: 5
+            }]
+        );
+    }
+
     #[test]
     fn duplicate_match() {
         assert_eq!(